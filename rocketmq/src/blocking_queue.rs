@@ -16,64 +16,430 @@
  */
 
 use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
 
-use tokio::sync::Mutex;
-use tokio::sync::Notify;
+use tokio::sync::Semaphore;
+use tokio::sync::SemaphorePermit;
+use tokio::sync::TryAcquireError;
 use tokio::time;
+use tokio_stream::Stream;
 
+/// Error returned by [`BlockingQueue::try_put`]. The item is always handed back so
+/// the caller can retry later, drop it, or route it elsewhere.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryPutError<T> {
+    /// The queue has no free capacity right now.
+    Full(T),
+    /// The queue has been [closed](BlockingQueue::close).
+    Closed(T),
+}
+
+impl<T> fmt::Display for TryPutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPutError::Full(_) => write!(f, "no available capacity"),
+            TryPutError::Closed(_) => write!(f, "queue is closed"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for TryPutError<T> {}
+
+/// Error returned by [`BlockingQueue::try_take`] when there is nothing to pop.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryTakeError {
+    /// The queue is empty but still open.
+    Empty,
+    /// The queue has been [closed](BlockingQueue::close) and fully drained.
+    Closed,
+}
+
+impl fmt::Display for TryTakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryTakeError::Empty => write!(f, "no items available"),
+            TryTakeError::Closed => write!(f, "queue is closed"),
+        }
+    }
+}
+
+impl std::error::Error for TryTakeError {}
+
+/// Error returned by [`BlockingQueue::put`] and [`BlockingQueue::take`] once the
+/// queue has been [closed](BlockingQueue::close).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct QueueClosed;
+
+impl fmt::Display for QueueClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "queue is closed")
+    }
+}
+
+impl std::error::Error for QueueClosed {}
+
+/// Error returned by [`BlockingQueue::try_reserve`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The queue has no free capacity right now.
+    Full,
+    /// The queue has been [closed](BlockingQueue::close).
+    Closed,
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::Full => write!(f, "no available capacity"),
+            TryReserveError::Closed => write!(f, "queue is closed"),
+        }
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+/// A bounded, FIFO blocking queue backed by two counting semaphores, mirroring the
+/// design of tokio's bounded mpsc channel: `empty_permits` tracks free slots (starts
+/// at `capacity`) and `filled_permits` tracks queued items (starts at `0`). `put`
+/// acquires an empty permit before pushing and releases a filled permit afterwards;
+/// `take` does the reverse. Each waiter is woken exactly when its resource becomes
+/// available, so there is no spurious wakeup racing between producers and consumers.
+///
+/// Calling [`close`](Self::close) unblocks every parked `put`/`take` the same way
+/// dropping the sender/receiver half of a tokio mpsc channel does: pending and
+/// future `put`s fail immediately, while `take` keeps draining whatever was already
+/// enqueued and only then starts reporting [`QueueClosed`].
 pub struct BlockingQueue<T> {
     queue: Mutex<VecDeque<T>>,
     capacity: usize,
-    notify: Notify,
+    empty_permits: Semaphore,
+    filled_permits: Semaphore,
 }
 
 impl<T> BlockingQueue<T> {
+    /// Creates a queue that holds up to `capacity` items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`: a zero-capacity queue can never have an empty
+    /// slot to acquire, so `put`/`take` (and every API built on them) would block
+    /// forever.
     pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "BlockingQueue capacity must be greater than zero");
         BlockingQueue {
             queue: Mutex::new(VecDeque::with_capacity(capacity)),
             capacity,
-            notify: Notify::new(),
+            empty_permits: Semaphore::new(capacity),
+            filled_permits: Semaphore::new(0),
         }
     }
 
-    pub async fn put(&self, item: T) {
-        loop {
-            {
-                let mut queue = self.queue.lock().await;
-                if queue.len() < self.capacity {
-                    queue.push_back(item);
-                    self.notify.notify_one(); // Notify only after successful push
-                    return;
-                }
-            }
-            self.notify.notified().await;
+    /// Returns the fixed capacity this queue was created with.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Pushes `item`, waiting for free capacity if the queue is full. Fails and
+    /// hands `item` back if the queue is or becomes [closed](Self::close).
+    pub async fn put(&self, item: T) -> Result<(), T> {
+        let permit = match self.empty_permits.acquire().await {
+            Ok(permit) => permit,
+            Err(_closed) => return Err(item),
+        };
+        permit.forget();
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.push_back(item);
         }
+        self.filled_permits.add_permits(1);
+        Ok(())
     }
 
     pub async fn offer(&self, item: T, timeout: std::time::Duration) -> bool {
-        time::timeout(timeout, self.put(item)).await.is_ok()
+        matches!(time::timeout(timeout, self.put(item)).await, Ok(Ok(())))
+    }
+
+    /// Non-blocking counterpart to [`put`](Self::put). Never awaits or arms a timer,
+    /// so it is cheap to call from hot paths that must not yield.
+    pub fn try_put(&self, item: T) -> Result<(), TryPutError<T>> {
+        let permit = match self.empty_permits.try_acquire() {
+            Ok(permit) => permit,
+            Err(TryAcquireError::NoPermits) => return Err(TryPutError::Full(item)),
+            Err(TryAcquireError::Closed) => return Err(TryPutError::Closed(item)),
+        };
+        permit.forget();
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.push_back(item);
+        }
+        self.filled_permits.add_permits(1);
+        Ok(())
     }
 
-    pub async fn take(&self) -> T {
-        loop {
+    /// Pops the oldest item, waiting if the queue is empty. Once
+    /// [closed](Self::close), keeps returning already-enqueued items and only
+    /// reports [`QueueClosed`] after the queue is drained.
+    pub async fn take(&self) -> Result<T, QueueClosed> {
+        match self.filled_permits.acquire().await {
+            Ok(permit) => {
+                permit.forget();
+                let item = {
+                    let mut queue = self.queue.lock().unwrap();
+                    queue
+                        .pop_front()
+                        .expect("filled permit acquired but queue is empty")
+                };
+                self.empty_permits.add_permits(1);
+                Ok(item)
+            }
+            // close() closes filled_permits together with empty_permits, so a
+            // closed error here just means we must drain by hand from now on.
+            Err(_closed) => {
+                let mut queue = self.queue.lock().unwrap();
+                queue.pop_front().ok_or(QueueClosed)
+            }
+        }
+    }
+
+    pub async fn poll(&self, timeout: std::time::Duration) -> Option<T> {
+        match time::timeout(timeout, self.take()).await {
+            Ok(Ok(item)) => Some(item),
+            Ok(Err(_closed)) => None,
+            Err(_elapsed) => None,
+        }
+    }
+
+    /// Non-blocking counterpart to [`take`](Self::take). Never awaits or arms a
+    /// timer, so it is cheap to call from hot paths that must not yield.
+    pub fn try_take(&self) -> Result<T, TryTakeError> {
+        match self.filled_permits.try_acquire() {
+            Ok(permit) => {
+                permit.forget();
+                let item = {
+                    let mut queue = self.queue.lock().unwrap();
+                    queue
+                        .pop_front()
+                        .expect("filled permit acquired but queue is empty")
+                };
+                self.empty_permits.add_permits(1);
+                Ok(item)
+            }
+            Err(TryAcquireError::NoPermits) => Err(TryTakeError::Empty),
+            Err(TryAcquireError::Closed) => {
+                let mut queue = self.queue.lock().unwrap();
+                queue.pop_front().ok_or(TryTakeError::Closed)
+            }
+        }
+    }
+
+    /// Closes the queue: every pending and future [`put`](Self::put) fails
+    /// immediately, and every pending and future [`take`](Self::take) keeps
+    /// draining already-enqueued items before reporting [`QueueClosed`]. Idempotent.
+    pub fn close(&self) {
+        self.empty_permits.close();
+        self.filled_permits.close();
+    }
+
+    /// Returns `true` once [`close`](Self::close) has been called.
+    pub fn is_closed(&self) -> bool {
+        self.empty_permits.is_closed()
+    }
+
+    /// Pushes every item in `items`, locking the queue once per `capacity`-sized
+    /// chunk instead of once per element. Blocks until enough slots free up for
+    /// each chunk. If the queue is closed partway through, the items that were not
+    /// yet pushed are returned.
+    pub async fn put_batch(&self, items: impl IntoIterator<Item = T>) -> Result<(), Vec<T>> {
+        let mut remaining: Vec<T> = items.into_iter().collect();
+        remaining.reverse(); // so draining off the end yields items in original order
+        while !remaining.is_empty() {
+            let chunk_len = remaining.len().min(self.capacity);
+            let permit = match self.empty_permits.acquire_many(chunk_len as u32).await {
+                Ok(permit) => permit,
+                Err(_closed) => {
+                    remaining.reverse();
+                    return Err(remaining);
+                }
+            };
+            permit.forget();
             {
-                let mut queue = self.queue.lock().await;
-                if let Some(item) = queue.pop_front() {
-                    self.notify.notify_one(); // Notify only after successful pop
-                    return item;
+                let mut queue = self.queue.lock().unwrap();
+                queue.extend(remaining.drain(remaining.len() - chunk_len..).rev());
+            }
+            self.filled_permits.add_permits(chunk_len);
+        }
+        Ok(())
+    }
+
+    /// Pops up to `max` already-present items without blocking, locking the queue
+    /// only once. Returns fewer than `max` items (possibly none) if the queue
+    /// currently holds less than that.
+    pub fn drain_up_to(&self, max: usize) -> Vec<T> {
+        if max == 0 {
+            return Vec::new();
+        }
+        // Reserve pop rights before touching the deque, the same way take/try_take
+        // do: each acquired filled permit corresponds to an item that was already
+        // pushed, so by the time we lock below every reserved item is guaranteed to
+        // be there. Reserving after locking (and reconciling permits afterwards)
+        // let a concurrent put land in the gap and hand out a phantom permit with
+        // nothing behind it.
+        let mut reserved = 0usize;
+        let mut closed = false;
+        while reserved < max {
+            match self.filled_permits.try_acquire() {
+                Ok(permit) => {
+                    permit.forget();
+                    reserved += 1;
+                }
+                Err(TryAcquireError::NoPermits) => break,
+                Err(TryAcquireError::Closed) => {
+                    closed = true;
+                    break;
                 }
             }
-            self.notify.notified().await;
         }
+        let mut queue = self.queue.lock().unwrap();
+        let mut drained: Vec<T> = (0..reserved)
+            .map(|_| {
+                queue
+                    .pop_front()
+                    .expect("filled permit acquired but queue is empty")
+            })
+            .collect();
+        // The permits semaphore is closed together with empty_permits once the
+        // queue is closed, so items enqueued before close() can no longer be
+        // claimed through a permit; drain them directly, same as take()'s fallback.
+        if closed && drained.len() < max {
+            let extra = (max - drained.len()).min(queue.len());
+            drained.extend(queue.drain(..extra));
+        }
+        drop(queue);
+        if reserved > 0 {
+            self.empty_permits.add_permits(reserved);
+        }
+        drained
     }
 
-    pub async fn poll(&self, timeout: std::time::Duration) -> Option<T> {
-        time::timeout(timeout, self.take()).await.ok()
+    /// Waits for at least one item, then greedily takes up to `max` items that are
+    /// already present, locking the queue at most twice regardless of `max`.
+    /// Returns an empty `Vec` once the queue is closed and drained.
+    pub async fn take_batch(&self, max: usize) -> Vec<T> {
+        if max == 0 {
+            return Vec::new();
+        }
+        match self.take().await {
+            Ok(first) => {
+                let mut items = Vec::with_capacity(max);
+                items.push(first);
+                items.extend(self.drain_up_to(max - 1));
+                items
+            }
+            Err(_closed) => Vec::new(),
+        }
+    }
+
+    /// Turns this queue into a [`Stream`] of items, driven by repeated calls to
+    /// [`take`](Self::take). The stream ends once the queue is
+    /// [closed](Self::close) and drained.
+    pub fn into_stream(self: Arc<Self>) -> QueueStream<T>
+    where
+        T: Send + 'static,
+    {
+        QueueStream {
+            queue: self,
+            pending: None,
+        }
+    }
+
+    /// Waits for a free slot and hands back a [`Permit`] that reserves it, so the
+    /// caller can prepare `item` (e.g. allocate a buffer) knowing the following
+    /// [`Permit::send`] cannot block or fail on capacity. Dropping the permit
+    /// without sending releases the slot back to the queue.
+    pub async fn reserve(&self) -> Result<Permit<'_, T>, QueueClosed> {
+        match self.empty_permits.acquire().await {
+            Ok(permit) => Ok(Permit {
+                queue: self,
+                permit: Some(permit),
+            }),
+            Err(_closed) => Err(QueueClosed),
+        }
+    }
+
+    /// Non-blocking counterpart to [`reserve`](Self::reserve).
+    pub fn try_reserve(&self) -> Result<Permit<'_, T>, TryReserveError> {
+        match self.empty_permits.try_acquire() {
+            Ok(permit) => Ok(Permit {
+                queue: self,
+                permit: Some(permit),
+            }),
+            Err(TryAcquireError::NoPermits) => Err(TryReserveError::Full),
+            Err(TryAcquireError::Closed) => Err(TryReserveError::Closed),
+        }
+    }
+}
+
+/// A reserved empty slot obtained from [`BlockingQueue::reserve`] or
+/// [`BlockingQueue::try_reserve`]. Call [`send`](Self::send) to push an item into
+/// the reserved slot; dropping the permit instead releases the slot back.
+pub struct Permit<'a, T> {
+    queue: &'a BlockingQueue<T>,
+    permit: Option<SemaphorePermit<'a>>,
+}
+
+impl<'a, T> Permit<'a, T> {
+    /// Pushes `item` into the slot this permit reserved. Never blocks or fails.
+    pub fn send(mut self, item: T) {
+        let permit = self.permit.take().unwrap();
+        permit.forget();
+        {
+            let mut queue = self.queue.queue.lock().unwrap();
+            queue.push_back(item);
+        }
+        self.queue.filled_permits.add_permits(1);
+    }
+}
+
+type TakeFuture<T> = Pin<Box<dyn Future<Output = Result<T, QueueClosed>> + Send>>;
+
+/// A [`Stream`] adapter over a [`BlockingQueue`], obtained via
+/// [`BlockingQueue::into_stream`]. Yields `None` once the queue is closed and
+/// drained, so it composes with the usual stream combinators (`.timeout(..)`,
+/// `.chunks_timeout(..)`, `.merge(..)`, `.map(..)`, ...).
+pub struct QueueStream<T> {
+    queue: Arc<BlockingQueue<T>>,
+    pending: Option<TakeFuture<T>>,
+}
+
+impl<T: Send + 'static> Stream for QueueStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let pending = this.pending.get_or_insert_with(|| {
+            let queue = Arc::clone(&this.queue);
+            Box::pin(async move { queue.take().await })
+        });
+        match pending.as_mut().poll(cx) {
+            Poll::Ready(result) => {
+                this.pending = None;
+                Poll::Ready(result.ok())
+            }
+            Poll::Pending => Poll::Pending,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use tokio::time::Duration;
 
     use super::*;
@@ -81,11 +447,23 @@ mod tests {
     #[tokio::test]
     async fn put_item_in_queue() {
         let queue = BlockingQueue::new(2);
-        queue.put(1).await;
-        let item = queue.take().await;
+        queue.put(1).await.unwrap();
+        let item = queue.take().await.unwrap();
         assert_eq!(item, 1);
     }
 
+    #[test]
+    fn capacity_reports_value_given_to_new() {
+        let queue: BlockingQueue<i32> = BlockingQueue::new(3);
+        assert_eq!(queue.capacity(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be greater than zero")]
+    fn new_rejects_zero_capacity() {
+        let _ = BlockingQueue::<()>::new(0);
+    }
+
     #[tokio::test]
     async fn offer_item_within_timeout() {
         let queue = BlockingQueue::new(1);
@@ -96,7 +474,7 @@ mod tests {
     #[tokio::test]
     async fn offer_item_exceeds_timeout() {
         let queue = BlockingQueue::new(1);
-        queue.put(1).await;
+        queue.put(1).await.unwrap();
         let result = queue.offer(2, Duration::from_millis(100)).await;
         assert!(!result);
     }
@@ -104,7 +482,7 @@ mod tests {
     #[tokio::test]
     async fn poll_item_within_timeout() {
         let queue = BlockingQueue::new(1);
-        queue.put(1).await;
+        queue.put(1).await.unwrap();
         let item = queue.poll(Duration::from_millis(100)).await;
         assert_eq!(item, Some(1));
     }
@@ -115,4 +493,204 @@ mod tests {
         let item = queue.poll(Duration::from_millis(100)).await;
         assert_eq!(item, None);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn put_wakes_only_one_blocked_consumer() {
+        // Regression test for the lost-wakeup bug the single-Notify design had: a
+        // blocked producer must never steal the wakeup meant for a blocked consumer.
+        let queue = Arc::new(BlockingQueue::new(1));
+
+        let consumer_queue = Arc::clone(&queue);
+        let consumer = tokio::spawn(async move { consumer_queue.take().await });
+
+        // Give the consumer a chance to park on the filled-permits semaphore.
+        tokio::task::yield_now().await;
+
+        queue.put(1).await.unwrap();
+        queue.put(2).await.unwrap();
+        assert_eq!(consumer.await.unwrap().unwrap(), 1);
+        assert_eq!(queue.take().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn try_put_succeeds_when_capacity_available() {
+        let queue = BlockingQueue::new(1);
+        assert_eq!(queue.try_put(1), Ok(()));
+        assert_eq!(queue.take().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn try_put_returns_item_when_full() {
+        let queue = BlockingQueue::new(1);
+        queue.put(1).await.unwrap();
+        assert_eq!(queue.try_put(2), Err(TryPutError::Full(2)));
+    }
+
+    #[tokio::test]
+    async fn try_take_returns_item_when_available() {
+        let queue = BlockingQueue::new(1);
+        queue.put(1).await.unwrap();
+        assert_eq!(queue.try_take(), Ok(1));
+    }
+
+    #[tokio::test]
+    async fn try_take_returns_err_when_empty() {
+        let queue = BlockingQueue::<()>::new(1);
+        assert_eq!(queue.try_take(), Err(TryTakeError::Empty));
+    }
+
+    #[tokio::test]
+    async fn put_fails_after_close() {
+        let queue = BlockingQueue::new(1);
+        queue.close();
+        assert_eq!(queue.put(1).await, Err(1));
+        assert_eq!(queue.try_put(1), Err(TryPutError::Closed(1)));
+    }
+
+    #[tokio::test]
+    async fn take_drains_remaining_items_then_reports_closed() {
+        let queue = BlockingQueue::new(2);
+        queue.put(1).await.unwrap();
+        queue.put(2).await.unwrap();
+        queue.close();
+
+        assert_eq!(queue.take().await, Ok(1));
+        assert_eq!(queue.take().await, Ok(2));
+        assert_eq!(queue.take().await, Err(QueueClosed));
+        assert_eq!(queue.try_take(), Err(TryTakeError::Closed));
+    }
+
+    #[tokio::test]
+    async fn close_wakes_a_blocked_take() {
+        let queue = Arc::new(BlockingQueue::<()>::new(1));
+        let waiter_queue = Arc::clone(&queue);
+        let waiter = tokio::spawn(async move { waiter_queue.take().await });
+
+        tokio::task::yield_now().await;
+        queue.close();
+
+        assert_eq!(waiter.await.unwrap(), Err(QueueClosed));
+        assert!(queue.is_closed());
+    }
+
+    #[tokio::test]
+    async fn put_batch_pushes_all_items() {
+        let queue = BlockingQueue::new(4);
+        queue.put_batch(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(queue.take().await.unwrap(), 1);
+        assert_eq!(queue.take().await.unwrap(), 2);
+        assert_eq!(queue.take().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn put_batch_chunks_larger_than_capacity() {
+        let queue = Arc::new(BlockingQueue::new(2));
+        let producer_queue = Arc::clone(&queue);
+        let producer =
+            tokio::spawn(async move { producer_queue.put_batch(vec![1, 2, 3, 4]).await });
+
+        let mut drained = Vec::new();
+        while drained.len() < 4 {
+            drained.push(queue.take().await.unwrap());
+        }
+        producer.await.unwrap().unwrap();
+        assert_eq!(drained, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn put_batch_returns_unsent_items_on_close() {
+        let queue = BlockingQueue::new(1);
+        queue.put(0).await.unwrap();
+        queue.close();
+        assert_eq!(queue.put_batch(vec![1, 2]).await, Err(vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn drain_up_to_returns_available_items_without_blocking() {
+        let queue = BlockingQueue::new(4);
+        queue.put_batch(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(queue.drain_up_to(2), vec![1, 2]);
+        assert_eq!(queue.drain_up_to(10), vec![3]);
+        assert_eq!(queue.drain_up_to(10), Vec::<i32>::new());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn drain_up_to_never_observes_a_phantom_permit_under_concurrent_puts() {
+        // Regression test: drain_up_to used to pop from the deque before its
+        // permit bookkeeping was reconciled, so a drain landing in the gap between
+        // a concurrent put()'s unlock and its add_permits(1) could hand out a
+        // filled permit with no item behind it, panicking the next take().
+        let queue = Arc::new(BlockingQueue::new(4));
+        let mut producers = Vec::new();
+        for i in 0..4 {
+            let producer_queue = Arc::clone(&queue);
+            producers.push(tokio::spawn(async move { producer_queue.put(i).await }));
+        }
+
+        let mut drained = Vec::new();
+        while drained.len() < 4 {
+            drained.extend(queue.drain_up_to(4));
+            tokio::task::yield_now().await;
+        }
+        for producer in producers {
+            producer.await.unwrap().unwrap();
+        }
+        drained.sort();
+        assert_eq!(drained, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn take_batch_waits_for_first_then_drains_rest() {
+        let queue = BlockingQueue::new(4);
+        queue.put_batch(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(queue.take_batch(2).await, vec![1, 2]);
+        assert_eq!(queue.take_batch(10).await, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn take_batch_returns_empty_once_closed_and_drained() {
+        let queue = BlockingQueue::<()>::new(1);
+        queue.close();
+        assert_eq!(queue.take_batch(4).await, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn stream_yields_items_then_ends_on_close() {
+        use tokio_stream::StreamExt;
+
+        let queue = Arc::new(BlockingQueue::new(4));
+        queue.put_batch(vec![1, 2, 3]).await.unwrap();
+        queue.close();
+
+        let items: Vec<_> = queue.into_stream().collect().await;
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn reserved_permit_send_pushes_item() {
+        let queue = BlockingQueue::new(1);
+        let permit = queue.reserve().await.unwrap();
+        permit.send(1);
+        assert_eq!(queue.take().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn dropping_a_permit_releases_its_slot() {
+        let queue = BlockingQueue::<()>::new(1);
+        let permit = queue.try_reserve().unwrap();
+        assert!(queue.try_reserve().is_err());
+        drop(permit);
+        assert!(queue.try_reserve().is_ok());
+    }
+
+    #[tokio::test]
+    async fn try_reserve_fails_when_full_or_closed() {
+        let queue = BlockingQueue::<()>::new(1);
+        let permit = queue.try_reserve().unwrap();
+        assert!(matches!(queue.try_reserve(), Err(TryReserveError::Full)));
+
+        drop(permit);
+        queue.close();
+        assert!(matches!(queue.try_reserve(), Err(TryReserveError::Closed)));
+    }
+}